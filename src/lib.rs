@@ -1,4 +1,6 @@
 mod winvec;
 mod winset;
-pub use winvec::WinVec;
+mod winvecof;
+pub use winvec::{WinVec, WinVecCheckpoint, WindowStats};
 pub use winset::WinSet;
+pub use winvecof::{Timestamped, WinVecOf};
@@ -1,10 +1,8 @@
 
-use std::{
-    iter::FromIterator,
-    time::{Duration, Instant},
-};
+use std::time::{Duration, Instant};
 
-use std::collections::HashSet;
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
 use std::hash::Hash;
 
 /// Windowed HashSet
@@ -14,25 +12,29 @@ use std::hash::Hash;
 /// Useful for rolling windows and other time based collections/caches.
 ///
 /// We purge old keys on read, rather than on insert.
+/// This can be switched to purge on insert via `set_eager_purge(true)`.
 /// You can specify the duration via `with_duration()`.
 /// Add elements with `insert` or `insert_with_timestamp`.
 /// View elements via `iter` and `into_iter`
 #[derive(Clone)]
-pub struct WinSet<T>(HashSet<(Instant, T)>, Duration);
+pub struct WinSet<T>(HashSet<(Instant, T)>, Duration, bool);
 
-impl<'a, T: Eq + Hash> WinSet<T> {
+impl<T: Eq + Hash> WinSet<T> {
     /// Create a new Windowed HashSet with a set duration
     pub fn with_duration(dur: Duration) -> Self {
-        WinSet(HashSet::new(), dur)
+        WinSet(HashSet::new(), dur, false)
     }
 
     /// insert an element into the windowed array
     pub fn insert(&mut self, el: T) {
-        self.0.insert((Instant::now(), el));
+        self.insert_with_timestamp(el, Instant::now());
     }
 
     /// insert an element with a specified timestamp
     pub fn insert_with_timestamp(&mut self, el: T, instant: Instant) {
+        if self.2 {
+            self.purge();
+        }
         self.0.insert((instant, el));
     }
 
@@ -40,35 +42,122 @@ impl<'a, T: Eq + Hash> WinSet<T> {
         let instant = Instant::now();
         let internal_set = set.into_iter().map(|el| (instant, el)).collect::<HashSet<_>>();
 
-        WinSet(internal_set, dur)
+        WinSet(internal_set, dur, false)
+    }
+
+    /// Create a new Windowed HashSet from elements with their own timestamps, so each one
+    /// expires individually rather than all at once.
+    pub fn from_set_with_timestamps(map: HashMap<T, Instant>, dur: Duration) -> Self {
+        let internal_set = map
+            .into_iter()
+            .map(|(el, instant)| (instant, el))
+            .collect::<HashSet<_>>();
+
+        WinSet(internal_set, dur, false)
     }
 
     pub fn duration(&self) -> Duration {
         self.1
     }
-}
 
-impl<'a, T: Clone + Eq + Hash> WinSet<T> {
-    /// Returns the number of elements within the collection.
-    /// We purge and then return the new length.
-    pub fn len(&mut self) -> usize {
+    /// Purge expired entries on every insert instead of only on read.
+    ///
+    /// Eager purging makes inserts O(n) but keeps memory bounded for write-heavy sets that are
+    /// rarely read. Lazy purging (the default) keeps inserts cheap.
+    pub fn set_eager_purge(&mut self, eager: bool) {
+        self.2 = eager;
+    }
+
+    /// Purges then keeps only the `n` live elements with the most recent timestamps.
+    pub fn retain_newest(&mut self, n: usize) {
         self.purge();
-        self.0.len()
+        if self.0.len() <= n {
+            return;
+        }
+
+        let mut entries = self.0.drain().collect::<Vec<_>>();
+        entries.sort_by_key(|e| Reverse(e.0));
+        entries.truncate(n);
+        self.0.extend(entries);
+    }
+
+    /// Purges then returns how long the live value has left before it expires, or `None` if it
+    /// is absent or already expired.
+    pub fn remaining(&mut self, value: &T) -> Option<Duration> {
+        self.purge();
+        let dur = self.1;
+        self.0
+            .iter()
+            .filter(|e| &e.1 == value)
+            .map(|e| dur.saturating_sub(e.0.elapsed()))
+            .max()
     }
 
     /// Purge expired entries by calculating elapsed time and filtering values past our specified
     /// duration.
     fn purge(&mut self) {
         let dur = self.1;
-        let set = &mut self.0;
+        self.0.retain(|e| e.0.elapsed() < dur);
+    }
 
-        let filtered = set.clone()
-            .into_iter()
-            .filter(|e| e.0.elapsed() < dur)
-            .map(|e| e.clone())
-            .collect();
+    /// Purges then removes the live elements for which `f` returns false.
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut f: F) {
+        self.purge();
+        self.0.retain(|e| f(&e.1));
+    }
+
+    /// Consume the set, returning the backing entries and duration without purging.
+    pub fn into_parts(self) -> (HashSet<(Instant, T)>, Duration) {
+        (self.0, self.1)
+    }
 
-        self.0 = filtered;
+    /// Rebuild a set from entries and a duration, e.g. those returned by `into_parts`.
+    ///
+    /// The entries are trusted as-is: they are not validated or purged. Eager purging is not
+    /// carried over.
+    pub fn from_parts(set: HashSet<(Instant, T)>, dur: Duration) -> Self {
+        WinSet(set, dur, false)
+    }
+
+    /// Insert every element of `set` stamped with the current time.
+    /// Elements that are already present have their timestamp refreshed.
+    pub fn extend_from_set(&mut self, set: HashSet<T>) {
+        let instant = Instant::now();
+        if self.2 {
+            self.purge();
+        }
+        self.0.retain(|e| !set.contains(&e.1));
+        self.0.extend(set.into_iter().map(|el| (instant, el)));
+    }
+
+    /// Purges both sets then returns the Jaccard similarity of their live values, i.e. the size of
+    /// the intersection over the size of the union. Two empty sets have a similarity of 0.0.
+    pub fn jaccard(&mut self, other: &mut WinSet<T>) -> f64 {
+        self.purge();
+        other.purge();
+
+        let ours = self.0.iter().map(|e| &e.1).collect::<HashSet<_>>();
+        let theirs = other.0.iter().map(|e| &e.1).collect::<HashSet<_>>();
+
+        let union = ours.union(&theirs).count();
+        if union == 0 {
+            return 0.0;
+        }
+        ours.intersection(&theirs).count() as f64 / union as f64
+    }
+}
+
+impl<'a, T: Clone + Eq + Hash> WinSet<T> {
+    /// Returns the number of elements within the collection.
+    /// We purge and then return the new length.
+    pub fn len(&mut self) -> usize {
+        self.purge();
+        self.0.len()
+    }
+
+    /// Returns true if the collection has no live elements.
+    pub fn is_empty(&mut self) -> bool {
+        self.len() == 0
     }
 
     /// Purges & Returns an Interator of the elements
@@ -76,9 +165,58 @@ impl<'a, T: Clone + Eq + Hash> WinSet<T> {
         self.purge();
         self.0.iter().map(|e| e.1.clone())
     }
+
+    /// Purges & Returns a snapshot of the live elements paired with their age, youngest first.
+    pub fn sorted_by_age(&mut self) -> Vec<(Duration, T)> {
+        self.purge();
+        let now = Instant::now();
+        let mut sorted = self
+            .0
+            .iter()
+            .map(|e| (now.saturating_duration_since(e.0), e.1.clone()))
+            .collect::<Vec<_>>();
+        sorted.sort_by_key(|e| e.0);
+        sorted
+    }
+
+    /// Purges both sets then returns the elements live in exactly one of them, carrying their
+    /// timestamps over from the side they came from.
+    /// The resulting set uses the longer of the two durations.
+    pub fn symmetric_difference(&mut self, other: &mut WinSet<T>) -> WinSet<T> {
+        self.purge();
+        other.purge();
+
+        let ours = self.0.iter().map(|e| &e.1).collect::<HashSet<_>>();
+        let theirs = other.0.iter().map(|e| &e.1).collect::<HashSet<_>>();
+
+        let set = self
+            .0
+            .iter()
+            .filter(|e| !theirs.contains(&e.1))
+            .chain(other.0.iter().filter(|e| !ours.contains(&e.1)))
+            .cloned()
+            .collect();
+
+        WinSet(set, self.1.max(other.1), false)
+    }
+
+    /// Removes and returns the expired elements, keeping the live ones in place.
+    /// The order of the returned elements is undefined.
+    pub fn drain_expired(&mut self) -> Vec<T> {
+        let dur = self.1;
+        let mut expired = Vec::new();
+        self.0.retain(|e| {
+            let live = e.0.elapsed() < dur;
+            if !live {
+                expired.push(e.1.clone());
+            }
+            live
+        });
+        expired
+    }
 }
 
-impl<'a, T: Clone + Eq + Hash> IntoIterator for WinSet<T> {
+impl<T: Clone + Eq + Hash> IntoIterator for WinSet<T> {
     type Item = T;
     type IntoIter = std::collections::hash_set::IntoIter<Self::Item>;
 
@@ -89,3 +227,169 @@ impl<'a, T: Clone + Eq + Hash> IntoIterator for WinSet<T> {
     }
 }
 
+impl<T: Eq + Hash> From<WinSet<T>> for HashSet<T> {
+    fn from(mut winset: WinSet<T>) -> Self {
+        winset.purge();
+        winset.0.into_iter().map(|e| e.1).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ago(secs: u64) -> Instant {
+        Instant::now() - Duration::from_secs(secs)
+    }
+
+    fn sorted(winset: &mut WinSet<i32>) -> Vec<i32> {
+        let mut values = winset.iter().collect::<Vec<_>>();
+        values.sort();
+        values
+    }
+
+    /// A one minute set holding `1..=4` inserted 40, 30, 20 and 10 seconds ago.
+    fn staggered() -> WinSet<i32> {
+        let map = [(1, 40), (2, 30), (3, 20), (4, 10)]
+            .iter()
+            .map(|&(el, secs)| (el, ago(secs)))
+            .collect::<HashMap<_, _>>();
+        WinSet::from_set_with_timestamps(map, Duration::from_secs(60))
+    }
+
+    #[test]
+    fn timestamped_import_expires_individually() {
+        let map = [(1, ago(90)), (2, ago(10))]
+            .iter()
+            .copied()
+            .collect::<HashMap<_, _>>();
+        let mut winset = WinSet::from_set_with_timestamps(map, Duration::from_secs(60));
+        assert_eq!(sorted(&mut winset), vec![2]);
+    }
+
+    #[test]
+    fn parts_round_trip() {
+        let mut winset = staggered();
+        assert_eq!(sorted(&mut winset), vec![1, 2, 3, 4]);
+
+        let mut winset = WinSet::from_parts(winset.into_parts().0, Duration::from_secs(25));
+        assert_eq!(sorted(&mut winset), vec![3, 4]);
+    }
+
+    #[test]
+    fn eager_purge_on_insert() {
+        let mut winset = WinSet::with_duration(Duration::from_secs(10));
+        winset.set_eager_purge(true);
+        for el in 0..100 {
+            winset.insert_with_timestamp(el, ago(20));
+        }
+        winset.insert(100);
+        assert_eq!(winset.0.len(), 1);
+    }
+
+    #[test]
+    fn retain_newest_keeps_freshest() {
+        let mut winset = staggered();
+        winset.retain_newest(2);
+        assert_eq!(sorted(&mut winset), vec![3, 4]);
+    }
+
+    #[test]
+    fn remaining_time_to_live() {
+        let mut winset = staggered();
+        let remaining = winset.remaining(&2).unwrap();
+        assert!((remaining.as_secs_f64() - 30.0).abs() < 0.1);
+        assert_eq!(winset.remaining(&5), None);
+    }
+
+    #[test]
+    fn retain_filters_live_values() {
+        let mut winset = WinSet::with_duration(Duration::from_secs(10));
+        winset.insert_with_timestamp(10, ago(20));
+        winset.insert(1);
+        winset.insert(5);
+        winset.insert(8);
+        winset.retain(|&v| v > 3);
+        assert_eq!(sorted(&mut winset), vec![5, 8]);
+    }
+
+    #[test]
+    fn drain_expired_returns_aged_out() {
+        let mut winset = WinSet::with_duration(Duration::from_secs(10));
+        winset.insert_with_timestamp(1, ago(20));
+        winset.insert_with_timestamp(2, ago(20));
+        winset.insert(3);
+        let mut expired = winset.drain_expired();
+        expired.sort();
+        assert_eq!(expired, vec![1, 2]);
+        assert_eq!(winset.0.len(), 1);
+    }
+
+    #[test]
+    fn extend_from_set_refreshes_timestamps() {
+        let mut winset = WinSet::with_duration(Duration::from_secs(60));
+        winset.insert_with_timestamp(1, ago(50));
+        winset.insert(2);
+        winset.extend_from_set([1, 3].iter().copied().collect());
+        assert_eq!(sorted(&mut winset), vec![1, 2, 3]);
+        assert!(winset.remaining(&1).unwrap() > Duration::from_secs(50));
+    }
+
+    #[test]
+    fn extend_from_set_purges_when_eager() {
+        let mut winset = WinSet::with_duration(Duration::from_secs(10));
+        winset.set_eager_purge(true);
+        winset.insert_with_timestamp(1, ago(20));
+        winset.extend_from_set([2, 3].iter().copied().collect());
+        assert_eq!(winset.0.len(), 2);
+    }
+
+    #[test]
+    fn jaccard_of_overlapping_sets() {
+        let mut a = staggered();
+        let mut b = WinSet::with_duration(Duration::from_secs(60));
+        b.insert(3);
+        b.insert(4);
+        b.insert(5);
+        b.insert_with_timestamp(1, ago(90));
+        assert_eq!(a.jaccard(&mut b), 0.4);
+
+        let mut empty = WinSet::<i32>::with_duration(Duration::from_secs(60));
+        assert_eq!(
+            empty.jaccard(&mut WinSet::with_duration(Duration::from_secs(1))),
+            0.0
+        );
+    }
+
+    #[test]
+    fn symmetric_difference_excludes_shared() {
+        let mut a = staggered();
+        let mut b = WinSet::with_duration(Duration::from_secs(90));
+        let five = ago(5);
+        b.insert(3);
+        b.insert_with_timestamp(5, five);
+        let mut diff = a.symmetric_difference(&mut b);
+        assert_eq!(diff.duration(), Duration::from_secs(90));
+        assert_eq!(sorted(&mut diff), vec![1, 2, 4, 5]);
+        assert!(diff.0.contains(&(five, 5)));
+    }
+
+    #[test]
+    fn sorted_by_age_youngest_first() {
+        let mut winset = staggered();
+        let values = winset
+            .sorted_by_age()
+            .into_iter()
+            .map(|e| e.1)
+            .collect::<Vec<_>>();
+        assert_eq!(values, vec![4, 3, 2, 1]);
+    }
+
+    #[test]
+    fn converts_into_hash_set() {
+        let mut winset = WinSet::with_duration(Duration::from_secs(10));
+        winset.insert_with_timestamp(1, ago(20));
+        winset.insert(2);
+        assert_eq!(HashSet::from(winset), [2].iter().copied().collect());
+    }
+}
@@ -1,7 +1,8 @@
-use std::{
-    iter::FromIterator,
-    time::{Duration, Instant},
-};
+use std::time::{Duration, Instant};
+
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
 
 /// Windowed Vector
 ///
@@ -10,41 +11,146 @@ use std::{
 /// Useful for rolling windows and other time based collections/caches.
 ///
 /// We purge old keys on read, rather than on insert.
+/// This can be switched to purge on insert via `set_eager_purge(true)`.
 /// You can specify the duration via `with_duration()`.
 /// Add elements with `push` or `push_with_timestamp`.
 /// View elements via `iter` and `into_iter`
+///
+/// A window can optionally be bounded via `with_duration_and_capacity()`, in which case `push`
+/// evicts the entry with the oldest timestamp once the window is full and `try_push` refuses the
+/// element instead. Once full, every push scans and shifts the backing store, so it costs O(n).
+///
+/// The window tracks whether its timestamps are still non-decreasing, so methods that cut off a
+/// prefix of old entries can binary search while it holds and fall back to a scan when it doesn't.
+#[derive(Clone)]
+pub struct WinVec<T>(Vec<(Instant, T)>, Duration, Option<usize>, bool, bool);
+
+/// Snapshot of a `WinVec` taken by `checkpoint`, to be handed back to `restore`
+///
+/// Entries keep their original timestamps, so expiry continues as if the snapshot was never
+/// taken.
 #[derive(Clone)]
-pub struct WinVec<T>(Vec<(Instant, T)>, Duration);
+pub struct WinVecCheckpoint<T>(WinVec<T>);
 
-impl<'a, T> WinVec<T> {
+/// Snapshot summary of a window's live entries
+///
+/// The numeric fields are only populated by `numeric_window_stats` on `WinVec<f64>`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WindowStats {
+    pub count: usize,
+    pub oldest_age: Option<Duration>,
+    pub newest_age: Option<Duration>,
+    pub span: Option<Duration>,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub mean: Option<f64>,
+}
+
+impl<T> WinVec<T> {
     /// Create a new Windowed Vector with a set duration
     pub fn with_duration(dur: Duration) -> Self {
-        WinVec(Vec::new(), dur)
+        WinVec(Vec::new(), dur, None, false, true)
+    }
+
+    /// Create a new Windowed Vector with a set duration that holds at most `cap` live elements
+    pub fn with_duration_and_capacity(dur: Duration, cap: usize) -> Self {
+        WinVec(Vec::new(), dur, Some(cap), false, true)
     }
 
     /// Push an element into the windowed array
+    /// If the window is bounded and full, the entry with the oldest timestamp is evicted in O(n).
     pub fn push(&mut self, el: T) {
-        self.0.push((Instant::now(), el));
+        self.push_with_timestamp(el, Instant::now());
     }
 
     /// Push an element with a specified timestamp
     pub fn push_with_timestamp(&mut self, el: T, instant: Instant) {
+        self.push_evicting(el, instant);
+    }
+
+    /// Push an element like `push`, returning the entry evicted to make room for it when the
+    /// window is bounded and full. The evicted entry is the one with the oldest timestamp.
+    ///
+    /// A window with a capacity of zero can't hold anything, so the element being pushed is
+    /// returned instead. Check `capacity()` if that must not be counted as an eviction.
+    pub fn push_reporting(&mut self, el: T) -> Option<T> {
+        self.push_evicting(el, Instant::now())
+    }
+
+    /// Push an element, evicting and returning the entry with the oldest timestamp if the window
+    /// is at capacity. A zero capacity window can't hold anything, so the element itself is
+    /// handed back.
+    fn push_evicting(&mut self, el: T, instant: Instant) -> Option<T> {
+        if self.3 {
+            self.purge();
+        }
+        self.push_bounded(el, instant)
+    }
+
+    /// Like `push_evicting`, minus the eager purge, so bulk inserts can purge once up front.
+    fn push_bounded(&mut self, el: T, instant: Instant) -> Option<T> {
+        if !self.at_capacity() {
+            self.push_entry(el, instant);
+            return None;
+        }
+        if self.0.is_empty() {
+            return Some(el);
+        }
+        let oldest = self
+            .0
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, e)| e.0)
+            .map_or(0, |(idx, _)| idx);
+        let evicted = self.0.remove(oldest).1;
+        self.push_entry(el, instant);
+        Some(evicted)
+    }
+
+    /// Append an entry, noting whether it keeps the timestamps sorted.
+    fn push_entry(&mut self, el: T, instant: Instant) {
+        if let Some(last) = self.0.last() {
+            self.4 &= last.0 <= instant;
+        }
         self.0.push((instant, el));
     }
 
+    /// Push an element unless the window is at capacity, in which case the element is handed
+    /// back. Unbounded windows always accept the element.
+    pub fn try_push(&mut self, el: T) -> Result<(), T> {
+        if self.3 {
+            self.purge();
+        }
+        if self.at_capacity() {
+            return Err(el);
+        }
+        self.push_entry(el, Instant::now());
+        Ok(())
+    }
+
     pub fn from_vec(vec: Vec<T>, dur: Duration) -> Self {
         let instant = Instant::now();
         let internal_vec = vec.into_iter().map(|el| (instant, el)).collect::<Vec<_>>();
 
-        WinVec(internal_vec, dur)
+        WinVec(internal_vec, dur, None, false, true)
     }
 
     pub fn duration(&self) -> Duration {
         self.1
     }
-}
 
-impl<'a, T: Clone> WinVec<T> {
+    pub fn capacity(&self) -> Option<usize> {
+        self.2
+    }
+
+    /// Purge expired entries on every push instead of only on read.
+    ///
+    /// Eager purging makes pushes O(n) but keeps memory bounded for write-heavy windows that are
+    /// rarely read. Lazy purging (the default) keeps pushes cheap.
+    pub fn set_eager_purge(&mut self, eager: bool) {
+        self.3 = eager;
+    }
+
     /// Returns the number of elements within the collection.
     /// We purge and then return the new length.
     pub fn len(&mut self) -> usize {
@@ -52,29 +158,716 @@ impl<'a, T: Clone> WinVec<T> {
         self.0.len()
     }
 
+    /// Returns true if the collection has no live elements.
+    pub fn is_empty(&mut self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of live elements pushed within the last `sub`.
+    /// Only timestamps are inspected, so this works for payload-less windows such as `WinVec<()>`.
+    pub fn count_within(&mut self, sub: Duration) -> usize {
+        self.purge();
+        self.0.iter().filter(|e| e.0.elapsed() < sub).count()
+    }
+
+    /// Drop every entry older than `dur` without changing the configured window duration.
+    ///
+    /// While timestamps are sorted the cut-off is found with a binary search in O(log n) before
+    /// draining the prefix. Out-of-order timestamps fall back to a full scan.
+    pub fn truncate_to_duration(&mut self, dur: Duration) {
+        if self.4 {
+            let idx = self.0.partition_point(|e| e.0.elapsed() >= dur);
+            self.0.drain(..idx);
+        } else {
+            self.0.retain(|e| e.0.elapsed() < dur);
+        }
+    }
+
+    /// Purges then keeps only the newest live entry for each key returned by `key_fn`.
+    /// Surviving entries keep their position and timestamp.
+    pub fn retain_latest_per_key<K: Eq + Hash, F: Fn(&T) -> K>(&mut self, key_fn: F) {
+        self.dedup_by_key(key_fn);
+    }
+
+    /// Purges then removes entries that share a key with a newer live entry.
+    /// Unlike `Vec::dedup_by_key` this looks across the whole window, not just consecutive
+    /// entries, and always keeps the freshest timestamp.
+    pub fn dedup_by_key<K: Eq + Hash, F: FnMut(&T) -> K>(&mut self, mut f: F) {
+        self.purge();
+
+        let mut latest: HashMap<K, (Instant, usize)> = HashMap::new();
+        for (idx, e) in self.0.iter().enumerate() {
+            let newest = latest.entry(f(&e.1)).or_insert((e.0, idx));
+            if e.0 >= newest.0 {
+                *newest = (e.0, idx);
+            }
+        }
+
+        let mut keep = vec![false; self.0.len()];
+        latest.values().for_each(|&(_, idx)| keep[idx] = true);
+        self.retain_mask(&keep);
+    }
+
+    /// Purge expired entries via `swap_remove`, avoiding the element shifting of an ordered purge.
+    ///
+    /// This destroys insertion order, so only use it when the window is treated as an unordered
+    /// multiset and don't mix it with order-dependent methods.
+    pub fn purge_unordered(&mut self) {
+        let dur = self.1;
+        let mut i = 0;
+        while i < self.0.len() {
+            if self.0[i].0.elapsed() >= dur {
+                self.0.swap_remove(i);
+            } else {
+                i += 1;
+            }
+        }
+        self.4 = self.is_sorted();
+    }
+
+    /// Keep only the entries whose position is marked in `keep`, preserving order.
+    fn retain_mask(&mut self, keep: &[bool]) {
+        let mut idx = 0;
+        self.0.retain(|_| {
+            idx += 1;
+            keep[idx - 1]
+        });
+    }
+
+    /// Returns true if timestamps are non-decreasing in insertion order.
+    /// This holds unless `push_with_timestamp` was given out-of-order instants.
+    pub fn is_sorted(&self) -> bool {
+        self.0.windows(2).all(|w| w[0].0 <= w[1].0)
+    }
+
     /// Purge expired entries by calculating elapsed time and filtering values past our specified
     /// duration.
     fn purge(&mut self) {
         let dur = self.1;
-        let vec = &mut self.0;
+        self.0.retain(|e| e.0.elapsed() < dur);
+    }
+
+    /// Returns true if the window is bounded and still full after purging.
+    /// We only purge when the backing store is full, so unbounded windows stay lazy.
+    fn at_capacity(&mut self) -> bool {
+        match self.2 {
+            Some(cap) if self.0.len() >= cap => {
+                self.purge();
+                self.0.len() >= cap
+            }
+            _ => false,
+        }
+    }
+
+    /// Purges then sums the live entries per key, where `f` maps each entry to a key and amount.
+    pub fn sum_by<K: Eq + Hash, F: Fn(&T) -> (K, f64)>(&mut self, f: F) -> HashMap<K, f64> {
+        self.purge();
+        let mut sums = HashMap::new();
+        for e in self.0.iter() {
+            let (key, amount) = f(&e.1);
+            *sums.entry(key).or_insert(0.0) += amount;
+        }
+        sums
+    }
+
+    /// Purges then returns the age of the live entry at `index`, or `None` if out of range.
+    pub fn age_at(&mut self, index: usize) -> Option<Duration> {
+        self.purge();
+        self.0.get(index).map(|e| e.0.elapsed())
+    }
+
+    /// Purges & Returns an Iterator of the timestamps of the live elements
+    pub fn timestamps(&mut self) -> impl '_ + Iterator<Item = Instant> {
+        self.purge();
+        self.0.iter().map(|e| e.0)
+    }
+
+    /// Purges then folds the live values in order, for building custom aggregates.
+    pub fn fold_live<B, F: FnMut(B, &T) -> B>(&mut self, init: B, mut f: F) -> B {
+        self.purge();
+        self.0.iter().fold(init, |acc, e| f(acc, &e.1))
+    }
+
+    /// Like `fold_live`, but also passes the age of each live entry, for custom time-decayed
+    /// aggregates.
+    pub fn fold_live_with_age<B, F: FnMut(B, Duration, &T) -> B>(
+        &mut self,
+        init: B,
+        mut f: F,
+    ) -> B {
+        self.purge();
+        self.0
+            .iter()
+            .fold(init, |acc, e| f(acc, e.0.elapsed(), &e.1))
+    }
+
+    /// Purges then removes the live elements for which `f` returns false, returning how many
+    /// were `(kept, removed)`.
+    pub fn retain_counted<F: FnMut(&T) -> bool>(&mut self, mut f: F) -> (usize, usize) {
+        self.purge();
+        let before = self.0.len();
+        self.0.retain(|e| f(&e.1));
+        (self.0.len(), before - self.0.len())
+    }
+
+    /// Consume the window, returning the backing entries and duration without purging.
+    pub fn into_parts(self) -> (Vec<(Instant, T)>, Duration) {
+        (self.0, self.1)
+    }
+
+    /// Rebuild a window from entries and a duration, e.g. those returned by `into_parts`.
+    ///
+    /// The entries are trusted as-is: they are not sorted, validated or purged. Capacity and
+    /// eager purging are not carried over.
+    pub fn from_parts(vec: Vec<(Instant, T)>, dur: Duration) -> Self {
+        let mut winvec = WinVec(vec, dur, None, false, true);
+        winvec.4 = winvec.is_sorted();
+        winvec
+    }
 
-        let filtered = vec
+    /// Purges then removes and returns the live values stamped before `cutoff`, in order.
+    /// The configured window duration is left unchanged.
+    pub fn evict_before(&mut self, cutoff: Instant) -> Vec<T> {
+        self.purge();
+        let (evicted, kept): (Vec<_>, Vec<_>) = std::mem::take(&mut self.0)
             .into_iter()
-            .filter(|e| e.0.elapsed() < dur)
-            .map(|e| e.clone())
-            .collect();
+            .partition(|e| e.0 < cutoff);
+        self.0 = kept;
+        evicted.into_iter().map(|e| e.1).collect()
+    }
+
+    /// Purges then returns true if the window is bounded and holds as many live elements as its
+    /// capacity. Unbounded windows are never full.
+    pub fn is_full(&mut self) -> bool {
+        self.purge();
+        matches!(self.2, Some(cap) if self.0.len() >= cap)
+    }
+
+    /// Purges then keeps only the `n` newest live entries by timestamp, preserving their order.
+    /// Combined with a long duration this turns the window into a ring buffer of the last `n`.
+    pub fn retain_last(&mut self, n: usize) {
+        self.purge();
+        if self.0.len() <= n {
+            return;
+        }
+
+        // Rank entries newest first, using insertion order to break timestamp ties
+        let mut order = (0..self.0.len()).collect::<Vec<_>>();
+        order.sort_by_key(|&idx| Reverse((self.0[idx].0, idx)));
 
-        self.0 = filtered;
+        let mut keep = vec![false; self.0.len()];
+        order.iter().take(n).for_each(|&idx| keep[idx] = true);
+        self.retain_mask(&keep);
     }
 
+    /// Purge only if the fraction of expired entries exceeds `expired_ratio`.
+    ///
+    /// While timestamps are sorted the expired entries form a prefix that we find with a binary
+    /// search in O(log n). Out-of-order timestamps fall back to counting them in O(n).
+    pub fn compact_if(&mut self, expired_ratio: f64) {
+        if self.0.is_empty() {
+            return;
+        }
+        let dur = self.1;
+        let expired = if self.4 {
+            self.0.partition_point(|e| e.0.elapsed() >= dur)
+        } else {
+            self.0.iter().filter(|e| e.0.elapsed() >= dur).count()
+        };
+        if expired as f64 / self.0.len() as f64 > expired_ratio {
+            self.purge();
+        }
+    }
+
+    /// Purges then returns the count and age summary of the live entries in a single pass.
+    pub fn window_stats(&mut self) -> WindowStats {
+        self.stats_by(|_| None)
+    }
+
+    /// Purges then summarises the live entries in a single pass, filling in the numeric fields
+    /// from the values `value` returns.
+    fn stats_by<F: Fn(&T) -> Option<f64>>(&mut self, value: F) -> WindowStats {
+        self.purge();
+        let now = Instant::now();
+        let mut stats = WindowStats {
+            count: self.0.len(),
+            oldest_age: None,
+            newest_age: None,
+            span: None,
+            min: None,
+            max: None,
+            mean: None,
+        };
+
+        let mut sum = None;
+        for e in self.0.iter() {
+            let age = now.saturating_duration_since(e.0);
+            stats.oldest_age = Some(stats.oldest_age.map_or(age, |o| o.max(age)));
+            stats.newest_age = Some(stats.newest_age.map_or(age, |n| n.min(age)));
+            if let Some(v) = value(&e.1) {
+                stats.min = Some(stats.min.map_or(v, |m| m.min(v)));
+                stats.max = Some(stats.max.map_or(v, |m| m.max(v)));
+                sum = Some(sum.unwrap_or(0.0) + v);
+            }
+        }
+
+        stats.span = stats.oldest_age.zip(stats.newest_age).map(|(o, n)| o - n);
+        stats.mean = sum.map(|sum| sum / stats.count as f64);
+        stats
+    }
+
+    /// Purges then removes and returns the oldest live values, in order, for as long as `f`
+    /// returns true.
+    pub fn drain_while<F: FnMut(&T) -> bool>(&mut self, mut f: F) -> Vec<T> {
+        self.purge();
+        let end = self.0.iter().position(|e| !f(&e.1)).unwrap_or(self.0.len());
+        self.0.drain(..end).map(|e| e.1).collect()
+    }
+
+    /// Purges then applies `f` to every live value in place, leaving timestamps untouched.
+    pub fn replace_all<F: FnMut(&mut T)>(&mut self, mut f: F) {
+        self.purge();
+        self.0.iter_mut().for_each(|e| f(&mut e.1));
+    }
+
+    /// Returns how many entries have expired but not been purged yet, without purging.
+    pub fn expired_len(&self) -> usize {
+        self.0.iter().filter(|e| e.0.elapsed() >= self.1).count()
+    }
+
+    /// Purges then merges consecutive live entries stamped less than `gap` apart using `combine`,
+    /// walking oldest to newest. Merged entries keep the later of the two timestamps.
+    /// An entry stamped before its predecessor counts as a zero gap, so it is always merged.
+    pub fn coalesce<F: FnMut(T, T) -> T>(&mut self, gap: Duration, mut combine: F) {
+        self.purge();
+        let mut merged: Vec<(Instant, T)> = Vec::with_capacity(self.0.len());
+        for (instant, el) in std::mem::take(&mut self.0) {
+            match merged.pop() {
+                Some((last, prev)) if instant.saturating_duration_since(last) < gap => {
+                    merged.push((instant.max(last), combine(prev, el)));
+                }
+                Some(prev) => {
+                    merged.push(prev);
+                    merged.push((instant, el));
+                }
+                None => merged.push((instant, el)),
+            }
+        }
+        self.0 = merged;
+    }
+
+    /// Returns the number of live elements without purging or touching the allocator.
+    /// This is an O(n) scan, unlike `len` which reclaims expired entries as it goes.
+    pub fn live_len(&self) -> usize {
+        self.0.iter().filter(|e| e.0.elapsed() < self.1).count()
+    }
+
+    /// Removes and returns the expired entries with their original timestamps, in order,
+    /// keeping the live ones. Useful for moving aged-out data into a longer lived archive.
+    pub fn drain_expired_with_timestamps(&mut self) -> Vec<(Instant, T)> {
+        let dur = self.1;
+        let (live, expired): (Vec<_>, Vec<_>) = std::mem::take(&mut self.0)
+            .into_iter()
+            .partition(|e| e.0.elapsed() < dur);
+        self.0 = live;
+        expired
+    }
+
+    /// Purges then returns the running accumulator after each live value, in order, as
+    /// `Iterator::scan`. The value returned by `f` becomes the new accumulator.
+    pub fn scan_live<S: Clone, F: FnMut(&mut S, &T) -> S>(&mut self, init: S, mut f: F) -> Vec<S> {
+        self.purge();
+        let mut acc = init;
+        self.0
+            .iter()
+            .map(|e| {
+                acc = f(&mut acc, &e.1);
+                acc.clone()
+            })
+            .collect()
+    }
+
+    /// Purges then returns a smoothly decaying count where each live entry contributes
+    /// `0.5^(elapsed/half_life)`. A zero `half_life` decays everything instantly.
+    pub fn decayed_count(&mut self, half_life: Duration) -> f64 {
+        self.purge();
+        if half_life.as_nanos() == 0 {
+            return 0.0;
+        }
+        let half_life = half_life.as_secs_f64();
+        self.0
+            .iter()
+            .map(|e| 0.5f64.powf(e.0.elapsed().as_secs_f64() / half_life))
+            .sum()
+    }
+
+    /// Removes the expired entries, handing each one to `f` without allocating.
+    /// Live entries keep their order, but expired ones are visited in no particular order.
+    pub fn for_each_expired<F: FnMut(Instant, T)>(&mut self, mut f: F) {
+        let dur = self.1;
+        let mut live = 0;
+        for idx in 0..self.0.len() {
+            if self.0[idx].0.elapsed() < dur {
+                self.0.swap(live, idx);
+                live += 1;
+            }
+        }
+        self.0
+            .drain(live..)
+            .for_each(|(instant, el)| f(instant, el));
+    }
+
+    /// Replace the current state with a snapshot taken by `checkpoint`.
+    pub fn restore(&mut self, cp: WinVecCheckpoint<T>) {
+        *self = cp.0;
+    }
+
+    /// Purges then returns the fraction of live entries pushed within the last `sub`.
+    /// An empty window has a ratio of 0.0.
+    pub fn window_ratio(&mut self, sub: Duration) -> f64 {
+        let recent = self.count_within(sub);
+        if self.0.is_empty() {
+            return 0.0;
+        }
+        recent as f64 / self.0.len() as f64
+    }
+
+    /// Merge entries into this window, leaving it sorted by timestamp.
+    /// When the window and `other` are both already sorted this is a linear merge in O(n + m);
+    /// otherwise everything is re-sorted in O((n + m) log(n + m)).
+    /// Bounded windows drop their oldest entries to stay within capacity.
+    pub fn merge_sorted(&mut self, mut other: Vec<(Instant, T)>) {
+        if !self.4 || !other.windows(2).all(|w| w[0].0 <= w[1].0) {
+            self.0.append(&mut other);
+            self.0.sort_by_key(|e| e.0);
+            self.4 = true;
+            self.truncate_to_capacity();
+            return;
+        }
+
+        let mut merged = Vec::with_capacity(self.0.len() + other.len());
+        let mut ours = std::mem::take(&mut self.0).into_iter().peekable();
+        let mut theirs = other.into_iter().peekable();
+        loop {
+            let take_ours = match (ours.peek(), theirs.peek()) {
+                (Some(a), Some(b)) => a.0 <= b.0,
+                (Some(_), None) => true,
+                (None, Some(_)) => false,
+                (None, None) => break,
+            };
+            let source = if take_ours { &mut ours } else { &mut theirs };
+            merged.extend(source.next());
+        }
+        self.0 = merged;
+        self.truncate_to_capacity();
+    }
+
+    /// Purge then drop the oldest entries of a sorted window that holds more than its capacity.
+    fn truncate_to_capacity(&mut self) {
+        if let Some(cap) = self.2 {
+            if self.0.len() > cap {
+                self.purge();
+                let excess = self.0.len().saturating_sub(cap);
+                self.0.drain(..excess);
+            }
+        }
+    }
+
+    /// Purges then estimates how long until half of the live entries have expired, assuming no
+    /// new pushes. Returns `None` for an empty window.
+    pub fn time_until_half_expired(&mut self) -> Option<Duration> {
+        self.purge();
+        let dur = self.1;
+        let mut remaining = self
+            .0
+            .iter()
+            .map(|e| dur.saturating_sub(e.0.elapsed()))
+            .collect::<Vec<_>>();
+        remaining.sort();
+        remaining.get(remaining.len().checked_sub(1)? / 2).copied()
+    }
+
+    /// Returns an Iterator over the entries that have expired but not been purged yet, i.e. those
+    /// the next purge would remove. Nothing is removed.
+    pub fn iter_expired(&self) -> impl '_ + Iterator<Item = &T> {
+        self.0
+            .iter()
+            .filter(move |e| e.0.elapsed() >= self.1)
+            .map(|e| &e.1)
+    }
+
+    /// Push every item stamped with a single `Instant::now()`, so the batch expires together.
+    pub fn push_batch(&mut self, items: impl IntoIterator<Item = T>) {
+        let instant = Instant::now();
+        if self.3 {
+            self.purge();
+        }
+        for el in items {
+            self.push_bounded(el, instant);
+        }
+    }
+}
+
+impl<'a, T: Clone> WinVec<T> {
     /// Purges & Returns an Interator of the elements
     pub fn iter(&'a mut self) -> impl 'a + Iterator<Item = T> {
         self.purge();
         self.0.iter().map(|e| e.1.clone())
     }
+
+    /// Purges & Returns a snapshot of the live elements paired with their age, youngest first.
+    pub fn sorted_by_age(&mut self) -> Vec<(Duration, T)> {
+        self.purge();
+        let now = Instant::now();
+        let mut sorted = self
+            .0
+            .iter()
+            .map(|e| (now.saturating_duration_since(e.0), e.1.clone()))
+            .collect::<Vec<_>>();
+        sorted.sort_by_key(|e| e.0);
+        sorted
+    }
+
+    /// Purges then splits the live values into those younger than `threshold` and those at least
+    /// as old, preserving order within each.
+    pub fn partition_by_age(&mut self, threshold: Duration) -> (Vec<T>, Vec<T>) {
+        self.purge();
+        let mut younger = Vec::new();
+        let mut older = Vec::new();
+        for e in self.0.iter() {
+            if e.0.elapsed() < threshold {
+                younger.push(e.1.clone());
+            } else {
+                older.push(e.1.clone());
+            }
+        }
+        (younger, older)
+    }
+
+    /// Purges then returns at most `max` live values sampled at an even stride across the window.
+    /// The oldest and newest values are always included when `max` is at least 2.
+    pub fn downsample(&mut self, max: usize) -> Vec<T> {
+        self.purge();
+        let len = self.0.len();
+        match max {
+            _ if len <= max => self.0.iter().map(|e| e.1.clone()).collect(),
+            0 => Vec::new(),
+            1 => vec![self.0[len - 1].1.clone()],
+            _ => (0..max)
+                .map(|i| self.0[i * (len - 1) / (max - 1)].1.clone())
+                .collect(),
+        }
+    }
+
+    /// Purges then returns the most recently pushed live value matching `f`.
+    pub fn find_latest<F: FnMut(&T) -> bool>(&mut self, mut f: F) -> Option<T> {
+        self.purge();
+        self.0.iter().rev().find(|e| f(&e.1)).map(|e| e.1.clone())
+    }
+
+    /// Purges then buckets the live entries by `key_fn`, keeping their timestamps so each bucket
+    /// can be turned back into its own window.
+    pub fn group_by<K: Eq + Hash, F: Fn(&T) -> K>(
+        &mut self,
+        key_fn: F,
+    ) -> HashMap<K, Vec<(Instant, T)>> {
+        self.purge();
+        let mut groups: HashMap<K, Vec<(Instant, T)>> = HashMap::new();
+        for e in self.0.iter() {
+            groups.entry(key_fn(&e.1)).or_default().push(e.clone());
+        }
+        groups
+    }
+
+    /// Push a clone of every element of `slice`, stamped with the current time.
+    pub fn extend_from_slice(&mut self, slice: &[T]) {
+        self.push_batch(slice.iter().cloned());
+    }
+
+    /// Purges `other` then pushes clones of its live entries, keeping their timestamps.
+    /// Unlike consuming `other`, this leaves the source window intact.
+    pub fn extend_win(&mut self, other: &mut WinVec<T>) {
+        other.purge();
+        if self.3 {
+            self.purge();
+        }
+        for e in other.0.iter() {
+            self.push_bounded(e.1.clone(), e.0);
+        }
+    }
+
+    /// Purges then returns the most recently pushed live value younger than `sub`, or `None` if
+    /// nothing that fresh exists.
+    pub fn sample_latest_within(&mut self, sub: Duration) -> Option<T> {
+        self.purge();
+        self.0
+            .iter()
+            .rev()
+            .find(|e| e.0.elapsed() < sub)
+            .map(|e| e.1.clone())
+    }
+
+    /// Purges then returns the live value with the greatest key, as `Iterator::max_by_key`.
+    pub fn max_by_key<K: Ord, F: FnMut(&T) -> K>(&mut self, mut f: F) -> Option<T> {
+        self.purge();
+        self.0.iter().max_by_key(|e| f(&e.1)).map(|e| e.1.clone())
+    }
+
+    /// Purges then returns the live value with the smallest key, as `Iterator::min_by_key`.
+    pub fn min_by_key<K: Ord, F: FnMut(&T) -> K>(&mut self, mut f: F) -> Option<T> {
+        self.purge();
+        self.0.iter().min_by_key(|e| f(&e.1)).map(|e| e.1.clone())
+    }
+
+    /// Purges & Returns an Iterator of the live elements pushed after `cutoff`
+    pub fn iter_since(&'a mut self, cutoff: Instant) -> impl 'a + Iterator<Item = T> {
+        self.purge();
+        self.0
+            .iter()
+            .filter(move |e| e.0 > cutoff)
+            .map(|e| e.1.clone())
+    }
+
+    /// Purges then removes the live elements for which `f` returns false, returning clones of the
+    /// surviving entries with their timestamps.
+    pub fn retain_into<F: FnMut(&T) -> bool>(&mut self, mut f: F) -> Vec<(Instant, T)> {
+        self.purge();
+        self.0.retain(|e| f(&e.1));
+        self.0.clone()
+    }
+
+    /// Purges then replaces the contents of `buf` with the live values in order, so a polling
+    /// loop can reuse one buffer.
+    pub fn clone_live_into(&mut self, buf: &mut Vec<T>) {
+        self.purge();
+        buf.clear();
+        buf.extend(self.0.iter().map(|e| e.1.clone()));
+    }
+
+    /// Purges & Returns an Iterator of the live elements in chunks of `size`, the last chunk
+    /// possibly shorter. Panics if `size` is 0, like `slice::chunks`.
+    pub fn chunks(&'a mut self, size: usize) -> impl 'a + Iterator<Item = Vec<T>> {
+        self.purge();
+        self.0
+            .chunks(size)
+            .map(|chunk| chunk.iter().map(|e| e.1.clone()).collect())
+    }
+
+    /// Snapshot the window, including expired entries, so it can be rolled back with `restore`.
+    pub fn checkpoint(&self) -> WinVecCheckpoint<T> {
+        WinVecCheckpoint(self.clone())
+    }
 }
 
-impl<'a, T: Clone> IntoIterator for WinVec<T> {
+impl WinVec<f64> {
+    /// Returns the sum of live samples, each weighted by `1 - elapsed/window`.
+    /// Fresh samples count fully while samples close to expiry count almost nothing.
+    pub fn age_weighted_sum(&mut self) -> f64 {
+        self.purge();
+        let window = self.1.as_secs_f64();
+        self.0
+            .iter()
+            .map(|e| e.1 * (1.0 - e.0.elapsed().as_secs_f64() / window))
+            .sum()
+    }
+
+    /// Purges then sums live samples from newest to oldest, stopping at the first sample for
+    /// which `f` returns false.
+    pub fn sum_while<F: FnMut(&f64) -> bool>(&mut self, mut f: F) -> f64 {
+        self.purge();
+        self.0.iter().rev().map(|e| e.1).take_while(|v| f(v)).sum()
+    }
+
+    /// Like `window_stats`, additionally filling in the min, max and mean of the live samples.
+    pub fn numeric_window_stats(&mut self) -> WindowStats {
+        self.stats_by(|&v| Some(v))
+    }
+
+    /// Purges then sums live samples into consecutive `bucket` sized time slices spanning the
+    /// window, oldest first. Empty buckets are zero, and a zero `bucket` yields no buckets.
+    pub fn bucketed_sum(&mut self, bucket: Duration) -> Vec<f64> {
+        self.purge();
+        let size = bucket.as_nanos();
+        if size == 0 {
+            return Vec::new();
+        }
+
+        let window = self.1.as_nanos();
+        let count = window.div_ceil(size) as usize;
+        let mut sums = vec![0.0; count];
+        let now = Instant::now();
+        for e in self.0.iter() {
+            let offset = window.saturating_sub(now.saturating_duration_since(e.0).as_nanos());
+            let idx = ((offset / size) as usize).min(count - 1);
+            sums[idx] += e.1;
+        }
+        sums
+    }
+}
+
+impl<T: PartialEq> WinVec<T> {
+    /// Purges then returns true if a live element equals `value`.
+    pub fn contains(&mut self, value: &T) -> bool {
+        self.purge();
+        self.0.iter().any(|e| &e.1 == value)
+    }
+}
+
+impl<T: Eq + Hash> WinVec<T> {
+    /// Purges then removes entries that repeat an earlier `(Instant, T)` pair exactly, such as
+    /// those left behind by replaying data through `push_with_timestamp`.
+    pub fn dedup_exact(&mut self) {
+        self.purge();
+        let mut seen = HashSet::new();
+        let keep = self.0.iter().map(|e| seen.insert(e)).collect::<Vec<_>>();
+        self.retain_mask(&keep);
+    }
+
+    /// Purges then keeps only the `n` most recently pushed distinct values, each collapsed to its
+    /// newest entry. Unlike `retain_last`, repeated values only count once.
+    pub fn retain_last_distinct(&mut self, n: usize) {
+        self.purge();
+
+        let mut latest: HashMap<&T, (Instant, usize)> = HashMap::new();
+        for (idx, e) in self.0.iter().enumerate() {
+            let newest = latest.entry(&e.1).or_insert((e.0, idx));
+            if e.0 >= newest.0 {
+                *newest = (e.0, idx);
+            }
+        }
+
+        let mut newest = latest.into_values().collect::<Vec<_>>();
+        newest.sort_by_key(|&v| Reverse(v));
+
+        let mut keep = vec![false; self.0.len()];
+        newest.iter().take(n).for_each(|&(_, idx)| keep[idx] = true);
+        self.retain_mask(&keep);
+    }
+}
+
+impl<T: Clone + Eq + Hash> WinVec<T> {
+    /// Purges then returns the `n` most frequent live values with their counts, most frequent
+    /// first. Ties go to the value seen most recently.
+    pub fn most_common(&mut self, n: usize) -> Vec<(T, usize)> {
+        self.purge();
+        let mut tally: HashMap<&T, (usize, usize)> = HashMap::new();
+        for (idx, e) in self.0.iter().enumerate() {
+            let entry = tally.entry(&e.1).or_insert((0, idx));
+            entry.0 += 1;
+            entry.1 = idx;
+        }
+
+        let mut counts = tally.into_iter().collect::<Vec<_>>();
+        counts.sort_by_key(|&(_, (count, last))| Reverse((count, last)));
+        counts
+            .into_iter()
+            .take(n)
+            .map(|(el, (count, _))| (el.clone(), count))
+            .collect()
+    }
+}
+
+impl<T: Clone> IntoIterator for WinVec<T> {
     type Item = T;
     type IntoIter = std::vec::IntoIter<Self::Item>;
 
@@ -85,3 +878,791 @@ impl<'a, T: Clone> IntoIterator for WinVec<T> {
     }
 }
 
+impl<T> From<WinVec<T>> for Vec<T> {
+    fn from(mut winvec: WinVec<T>) -> Self {
+        winvec.purge();
+        winvec.0.into_iter().map(|e| e.1).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ago(secs: u64) -> Instant {
+        Instant::now() - Duration::from_secs(secs)
+    }
+
+    fn approx(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-3
+    }
+
+    /// A one minute window holding `1..=4` pushed 40, 30, 20 and 10 seconds ago.
+    fn staggered() -> WinVec<i32> {
+        let mut winvec = WinVec::with_duration(Duration::from_secs(60));
+        for (el, secs) in [(1, 40), (2, 30), (3, 20), (4, 10)].iter() {
+            winvec.push_with_timestamp(*el, ago(*secs));
+        }
+        winvec
+    }
+
+    #[test]
+    fn try_push_respects_capacity() {
+        let mut winvec = WinVec::with_duration_and_capacity(Duration::from_secs(10), 2);
+        winvec.push(1);
+        winvec.push(2);
+        assert_eq!(winvec.try_push(3), Err(3));
+        assert_eq!(winvec.iter().collect::<Vec<_>>(), vec![1, 2]);
+
+        let mut winvec = WinVec::with_duration_and_capacity(Duration::from_secs(10), 2);
+        winvec.push_with_timestamp(1, ago(20));
+        winvec.push(2);
+        assert_eq!(winvec.try_push(3), Ok(()));
+        assert_eq!(winvec.iter().collect::<Vec<_>>(), vec![2, 3]);
+    }
+
+    #[test]
+    fn huge_capacity_does_not_preallocate() {
+        let mut winvec = WinVec::with_duration_and_capacity(Duration::from_secs(10), usize::MAX);
+        winvec.push(1);
+        assert_eq!(winvec.iter().collect::<Vec<_>>(), vec![1]);
+    }
+
+    #[test]
+    fn push_evicts_oldest_timestamp() {
+        let mut winvec = WinVec::with_duration_and_capacity(Duration::from_secs(60), 2);
+        winvec.push(1);
+        winvec.push_with_timestamp(2, ago(50));
+        winvec.push(3);
+        assert_eq!(winvec.iter().collect::<Vec<_>>(), vec![1, 3]);
+    }
+
+    #[test]
+    fn truncate_to_duration_keeps_window() {
+        let mut winvec = WinVec::with_duration(Duration::from_secs(60));
+        winvec.push_with_timestamp(1, ago(30));
+        winvec.push_with_timestamp(2, ago(20));
+        winvec.push_with_timestamp(3, ago(5));
+        winvec.truncate_to_duration(Duration::from_secs(25));
+        assert_eq!(winvec.iter().collect::<Vec<_>>(), vec![2, 3]);
+        assert_eq!(winvec.duration(), Duration::from_secs(60));
+    }
+
+    #[test]
+    fn truncate_to_duration_scans_unsorted_window() {
+        let mut winvec = WinVec::with_duration(Duration::from_secs(60));
+        for (el, secs) in [(1, 30), (2, 5), (3, 30), (4, 10)].iter() {
+            winvec.push_with_timestamp(*el, ago(*secs));
+        }
+        winvec.truncate_to_duration(Duration::from_secs(25));
+        assert_eq!(winvec.iter().collect::<Vec<_>>(), vec![2, 4]);
+    }
+
+    #[test]
+    fn push_reporting_returns_evicted() {
+        let mut winvec = WinVec::with_duration_and_capacity(Duration::from_secs(60), 2);
+        assert_eq!(winvec.push_reporting(1), None);
+        assert_eq!(winvec.push_reporting(2), None);
+        assert_eq!(winvec.push_reporting(3), Some(1));
+        assert_eq!(winvec.iter().collect::<Vec<_>>(), vec![2, 3]);
+
+        let mut winvec = WinVec::with_duration_and_capacity(Duration::from_secs(60), 0);
+        assert_eq!(winvec.push_reporting(1), Some(1));
+        assert!(winvec.is_empty());
+    }
+
+    #[test]
+    fn eager_purge_bounds_backing_store() {
+        let mut winvec = WinVec::with_duration(Duration::from_secs(10));
+        winvec.set_eager_purge(true);
+        for i in 0..100 {
+            winvec.push_with_timestamp(i, ago(20));
+        }
+        winvec.push(100);
+        assert_eq!(winvec.0.len(), 1);
+    }
+
+    #[test]
+    fn window_stats_match_entries() {
+        let mut winvec = WinVec::with_duration(Duration::from_secs(60));
+        assert_eq!(winvec.numeric_window_stats().count, 0);
+        assert_eq!(winvec.numeric_window_stats().mean, None);
+
+        winvec.push_with_timestamp(2.0, ago(30));
+        winvec.push_with_timestamp(6.0, ago(10));
+        winvec.push_with_timestamp(4.0, ago(20));
+        let stats = winvec.numeric_window_stats();
+        assert_eq!(stats.count, 3);
+        assert_eq!(stats.oldest_age.unwrap().as_secs(), 30);
+        assert_eq!(stats.newest_age.unwrap().as_secs(), 10);
+        assert_eq!(stats.span.unwrap().as_secs(), 20);
+        assert_eq!(stats.min, Some(2.0));
+        assert_eq!(stats.max, Some(6.0));
+        assert_eq!(stats.mean, Some(4.0));
+        assert_eq!(winvec.window_stats().mean, None);
+    }
+
+    #[test]
+    fn coalesce_merges_bursts() {
+        let mut winvec = WinVec::with_duration(Duration::from_secs(60));
+        let newest = ago(10);
+        winvec.push_with_timestamp(1, newest - Duration::from_millis(200));
+        winvec.push_with_timestamp(2, newest - Duration::from_millis(100));
+        winvec.push_with_timestamp(3, newest);
+        winvec.coalesce(Duration::from_secs(1), |a, b| a + b);
+        assert_eq!(winvec.iter().collect::<Vec<_>>(), vec![6]);
+        assert_eq!(winvec.timestamps().collect::<Vec<_>>(), vec![newest]);
+
+        let mut winvec = WinVec::with_duration(Duration::from_secs(60));
+        winvec.push_with_timestamp(1, ago(30));
+        winvec.push_with_timestamp(2, ago(20));
+        winvec.push_with_timestamp(3, ago(10));
+        winvec.coalesce(Duration::from_secs(1), |a, b| a + b);
+        assert_eq!(winvec.iter().collect::<Vec<_>>(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn coalesce_keeps_later_timestamp_out_of_order() {
+        let mut winvec = WinVec::with_duration(Duration::from_secs(60));
+        let newer = ago(1);
+        winvec.push_with_timestamp(1, newer);
+        winvec.push_with_timestamp(2, ago(5));
+        winvec.coalesce(Duration::from_secs(1), |a, b| a + b);
+        assert_eq!(winvec.iter().collect::<Vec<_>>(), vec![3]);
+        assert_eq!(winvec.timestamps().collect::<Vec<_>>(), vec![newer]);
+    }
+
+    #[test]
+    fn push_batch_shares_timestamp() {
+        let mut winvec = WinVec::with_duration(Duration::from_secs(10));
+        winvec.push_batch(vec![1, 2, 3]);
+        let timestamps = winvec.timestamps().collect::<Vec<_>>();
+        assert_eq!(timestamps.len(), 3);
+        assert!(timestamps.iter().all(|&t| t == timestamps[0]));
+    }
+
+    #[test]
+    fn push_batch_purges_once_when_eager() {
+        let mut winvec = WinVec::with_duration(Duration::from_secs(10));
+        winvec.set_eager_purge(true);
+        winvec.push_with_timestamp(0, ago(20));
+        winvec.push_batch(vec![1, 2, 3]);
+        assert_eq!(
+            winvec.0.iter().map(|e| e.1).collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+    }
+
+    #[test]
+    fn purges_expired_on_read() {
+        let mut winvec = WinVec::with_duration(Duration::from_secs(10));
+        winvec.push_with_timestamp(1, ago(20));
+        winvec.push(2);
+        assert_eq!(winvec.len(), 1);
+        assert_eq!(winvec.into_iter().collect::<Vec<_>>(), vec![2]);
+    }
+
+    #[test]
+    fn age_weighted_sum_favours_newer() {
+        let mut winvec = WinVec::with_duration(Duration::from_secs(10));
+        winvec.push_with_timestamp(1.0, ago(8));
+        let old = winvec.age_weighted_sum();
+        winvec.push_with_timestamp(1.0, ago(2));
+        let both = winvec.age_weighted_sum();
+        assert!(both - old > old);
+        assert!((old - 0.2).abs() < 0.01);
+    }
+
+    #[test]
+    fn unit_values_count_events() {
+        let mut winvec = WinVec::with_duration(Duration::from_secs(60));
+        winvec.push_with_timestamp((), ago(90));
+        winvec.push_with_timestamp((), ago(30));
+        winvec.push(());
+        winvec.push(());
+        assert_eq!(winvec.len(), 3);
+        assert_eq!(winvec.count_within(Duration::from_secs(10)), 2);
+    }
+
+    #[test]
+    fn purge_unordered_keeps_live_multiset() {
+        let mut winvec = WinVec::with_duration(Duration::from_secs(10));
+        winvec.push_with_timestamp(1, ago(20));
+        winvec.push(2);
+        winvec.push_with_timestamp(3, ago(20));
+        winvec.push(4);
+        winvec.push(2);
+        winvec.purge_unordered();
+        let mut live = winvec.iter().collect::<Vec<_>>();
+        live.sort();
+        assert_eq!(live, vec![2, 2, 4]);
+    }
+
+    #[test]
+    fn sorted_by_age_youngest_first() {
+        let mut winvec = WinVec::with_duration(Duration::from_secs(60));
+        winvec.push_with_timestamp(1, ago(30));
+        winvec.push_with_timestamp(2, ago(90));
+        winvec.push_with_timestamp(3, ago(10));
+        winvec.push_with_timestamp(4, ago(20));
+        let sorted = winvec.sorted_by_age();
+        assert_eq!(
+            sorted.iter().map(|e| e.1).collect::<Vec<_>>(),
+            vec![3, 4, 1]
+        );
+        assert!(sorted.windows(2).all(|w| w[0].0 <= w[1].0));
+    }
+
+    #[test]
+    fn retain_latest_per_key_keeps_newest() {
+        let mut winvec = WinVec::with_duration(Duration::from_secs(60));
+        let newest = ago(5);
+        winvec.push_with_timestamp(("a", 1), ago(30));
+        winvec.push_with_timestamp(("b", 1), ago(20));
+        winvec.push_with_timestamp(("a", 2), newest);
+        winvec.push_with_timestamp(("a", 3), ago(10));
+        winvec.retain_latest_per_key(|e| e.0);
+        assert_eq!(winvec.iter().collect::<Vec<_>>(), vec![("b", 1), ("a", 2)]);
+        assert_eq!(winvec.0[1].0, newest);
+    }
+
+    #[test]
+    fn dedup_by_key_is_global() {
+        let mut winvec = WinVec::with_duration(Duration::from_secs(60));
+        winvec.push_with_timestamp(10, ago(30));
+        winvec.push_with_timestamp(21, ago(20));
+        winvec.push_with_timestamp(12, ago(10));
+        winvec.push_with_timestamp(23, ago(40));
+        winvec.dedup_by_key(|v| v / 10);
+        assert_eq!(winvec.iter().collect::<Vec<_>>(), vec![21, 12]);
+    }
+
+    #[test]
+    fn partition_by_age_splits_on_threshold() {
+        let mut winvec = staggered();
+        let (younger, older) = winvec.partition_by_age(Duration::from_secs(25));
+        assert_eq!(younger, vec![3, 4]);
+        assert_eq!(older, vec![1, 2]);
+    }
+
+    #[test]
+    fn downsample_includes_endpoints() {
+        let mut winvec = WinVec::with_duration(Duration::from_secs(60));
+        for el in 0..100 {
+            winvec.push(el);
+        }
+        let sampled = winvec.downsample(10);
+        assert_eq!(sampled, vec![0, 11, 22, 33, 44, 55, 66, 77, 88, 99]);
+        assert_eq!(winvec.downsample(200).len(), 100);
+    }
+
+    #[test]
+    fn find_latest_returns_newest_match() {
+        let mut winvec = staggered();
+        assert_eq!(winvec.find_latest(|v| v % 2 == 1), Some(3));
+        assert_eq!(winvec.find_latest(|&v| v > 10), None);
+    }
+
+    #[test]
+    fn group_by_keeps_timestamps() {
+        let mut winvec = WinVec::with_duration(Duration::from_secs(60));
+        let a = ago(10);
+        winvec.push_with_timestamp(("a", 1.0), a);
+        winvec.push_with_timestamp(("b", 2.0), ago(20));
+        winvec.push_with_timestamp(("a", 3.0), ago(5));
+        winvec.push_with_timestamp(("a", 100.0), ago(90));
+
+        let groups = winvec.group_by(|e| e.0);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups["a"].len(), 2);
+        assert_eq!(groups["a"][0], (a, ("a", 1.0)));
+        assert_eq!(groups["b"].len(), 1);
+    }
+
+    #[test]
+    fn sum_by_excludes_expired() {
+        let mut winvec = WinVec::with_duration(Duration::from_secs(60));
+        winvec.push_with_timestamp(("a", 1.0), ago(10));
+        winvec.push_with_timestamp(("b", 2.0), ago(20));
+        winvec.push_with_timestamp(("a", 3.0), ago(5));
+        winvec.push_with_timestamp(("a", 100.0), ago(90));
+
+        let sums = winvec.sum_by(|e| (e.0, e.1));
+        assert_eq!(sums.len(), 2);
+        assert_eq!(sums["a"], 4.0);
+        assert_eq!(sums["b"], 2.0);
+    }
+
+    #[test]
+    fn converts_into_vec() {
+        let mut winvec = WinVec::with_duration(Duration::from_secs(10));
+        winvec.push_with_timestamp(1, ago(20));
+        winvec.push(2);
+        assert_eq!(Vec::from(winvec), vec![2]);
+    }
+
+    #[test]
+    fn extend_from_slice_pushes_clones() {
+        let mut winvec = WinVec::with_duration(Duration::from_secs(10));
+        let slice = [1, 2, 3];
+        winvec.push_with_timestamp(0, ago(20));
+        winvec.extend_from_slice(&slice);
+        assert_eq!(winvec.iter().collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(slice, [1, 2, 3]);
+    }
+
+    #[test]
+    fn extend_from_slice_shares_timestamp() {
+        let mut winvec = WinVec::with_duration(Duration::from_secs(10));
+        winvec.extend_from_slice(&[1, 2, 3]);
+        assert!(winvec.0.iter().all(|e| e.0 == winvec.0[0].0));
+    }
+
+    #[test]
+    fn contains_only_live_values() {
+        let mut winvec = WinVec::with_duration(Duration::from_secs(10));
+        winvec.push_with_timestamp(1, ago(20));
+        winvec.push(2);
+        assert!(winvec.contains(&2));
+        assert!(!winvec.contains(&1));
+        assert!(!winvec.contains(&3));
+    }
+
+    #[test]
+    fn age_at_follows_position() {
+        let mut winvec = staggered();
+        let first = winvec.age_at(0).unwrap();
+        let last = winvec.age_at(3).unwrap();
+        assert!(first > last);
+        assert_eq!(first.as_secs(), 40);
+        assert_eq!(winvec.age_at(4), None);
+    }
+
+    #[test]
+    fn timestamps_skip_expired() {
+        let mut winvec = WinVec::with_duration(Duration::from_secs(60));
+        let live = ago(10);
+        winvec.push_with_timestamp(1, ago(90));
+        winvec.push_with_timestamp(2, live);
+        assert_eq!(winvec.timestamps().collect::<Vec<_>>(), vec![live]);
+    }
+
+    #[test]
+    fn folds_over_live_values() {
+        let mut winvec = staggered();
+        winvec.push_with_timestamp(100, ago(90));
+        assert_eq!(winvec.fold_live(1, |acc, v| acc * v), 24);
+
+        #[derive(Default)]
+        struct Acc {
+            count: usize,
+            max: i32,
+        }
+        let acc = winvec.fold_live(Acc::default(), |acc, &v| Acc {
+            count: acc.count + 1,
+            max: acc.max.max(v),
+        });
+        assert_eq!((acc.count, acc.max), (4, 4));
+    }
+
+    #[test]
+    fn fold_live_with_age_matches_age_weighted_sum() {
+        let mut winvec = WinVec::with_duration(Duration::from_secs(10));
+        winvec.push_with_timestamp(2.0, ago(5));
+        winvec.push_with_timestamp(4.0, ago(2));
+        let window = winvec.duration().as_secs_f64();
+        let folded = winvec.fold_live_with_age(0.0, |acc, age, v| {
+            acc + v * (1.0 - age.as_secs_f64() / window)
+        });
+        assert!((folded - winvec.age_weighted_sum()).abs() < 0.01);
+    }
+
+    #[test]
+    fn extend_win_leaves_source_intact() {
+        let mut source = staggered();
+        let mut winvec = WinVec::with_duration(Duration::from_secs(60));
+        winvec.push(5);
+        winvec.extend_win(&mut source);
+        assert_eq!(source.iter().collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+        assert_eq!(winvec.iter().collect::<Vec<_>>(), vec![5, 1, 2, 3, 4]);
+        assert_eq!(
+            winvec.timestamps().skip(1).collect::<Vec<_>>(),
+            source.timestamps().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn extend_win_purges_once_when_eager() {
+        let mut source = staggered();
+        let mut winvec = WinVec::with_duration_and_capacity(Duration::from_secs(60), 3);
+        winvec.set_eager_purge(true);
+        winvec.push_with_timestamp(0, ago(90));
+        winvec.extend_win(&mut source);
+        assert_eq!(
+            winvec.0.iter().map(|e| e.1).collect::<Vec<_>>(),
+            vec![2, 3, 4]
+        );
+    }
+
+    #[test]
+    fn sample_latest_within_rejects_stale() {
+        let mut winvec = staggered();
+        assert_eq!(winvec.sample_latest_within(Duration::from_secs(5)), None);
+        assert_eq!(
+            winvec.sample_latest_within(Duration::from_secs(15)),
+            Some(4)
+        );
+    }
+
+    #[test]
+    fn min_and_max_by_key() {
+        let mut winvec = WinVec::with_duration(Duration::from_secs(60));
+        assert_eq!(winvec.max_by_key(|&v: &i32| v), None);
+        winvec.push(-5);
+        winvec.push(3);
+        winvec.push(-1);
+        assert_eq!(winvec.max_by_key(|v: &i32| v.abs()), Some(-5));
+        assert_eq!(winvec.min_by_key(|v: &i32| v.abs()), Some(-1));
+    }
+
+    #[test]
+    fn iter_since_yields_later_batch() {
+        let mut winvec = WinVec::with_duration(Duration::from_secs(60));
+        winvec.push_with_timestamp(1, ago(20));
+        winvec.push_with_timestamp(2, ago(20));
+        let cutoff = ago(10);
+        winvec.push(3);
+        winvec.push(4);
+        assert_eq!(winvec.iter_since(cutoff).collect::<Vec<_>>(), vec![3, 4]);
+    }
+
+    #[test]
+    fn retain_counted_reports_counts() {
+        let mut winvec = staggered();
+        assert_eq!(winvec.retain_counted(|v| v % 2 == 0), (2, 2));
+        assert_eq!(winvec.iter().collect::<Vec<_>>(), vec![2, 4]);
+    }
+
+    #[test]
+    fn parts_round_trip() {
+        let winvec = staggered();
+        let (vec, dur) = winvec.into_parts();
+        let mut rebuilt = WinVec::from_parts(vec.clone(), dur);
+        assert_eq!(
+            rebuilt.timestamps().collect::<Vec<_>>(),
+            vec.iter().map(|e| e.0).collect::<Vec<_>>()
+        );
+        assert_eq!(rebuilt.iter().collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn evict_before_splits_on_cutoff() {
+        let mut winvec = staggered();
+        assert_eq!(winvec.evict_before(ago(25)), vec![1, 2]);
+        assert_eq!(winvec.iter().collect::<Vec<_>>(), vec![3, 4]);
+        assert_eq!(winvec.duration(), Duration::from_secs(60));
+    }
+
+    #[test]
+    fn is_full_tracks_capacity() {
+        assert!(!WinVec::<i32>::with_duration(Duration::from_secs(10)).is_full());
+
+        let window = Duration::from_secs(10);
+        let mut winvec = WinVec::with_duration_and_capacity(window, 2);
+        winvec.push_with_timestamp(1, ago(20));
+        winvec.push(2);
+        assert!(!winvec.is_full());
+
+        // An entry can't be made to expire without waiting, so this one is pushed 200ms before
+        // the end of the window and the test sleeps past it.
+        winvec.push_with_timestamp(3, Instant::now() - window + Duration::from_millis(200));
+        assert!(winvec.is_full());
+        std::thread::sleep(Duration::from_millis(300));
+        assert!(!winvec.is_full());
+    }
+
+    #[test]
+    fn retain_last_keeps_newest_in_order() {
+        let mut winvec = WinVec::with_duration(Duration::from_secs(60));
+        winvec.push_with_timestamp(1, ago(10));
+        winvec.push_with_timestamp(2, ago(40));
+        winvec.push_with_timestamp(3, ago(20));
+        winvec.push_with_timestamp(4, ago(30));
+        winvec.retain_last(2);
+        assert_eq!(winvec.iter().collect::<Vec<_>>(), vec![1, 3]);
+    }
+
+    #[test]
+    fn sum_while_stops_at_first_failure() {
+        let mut winvec = WinVec::with_duration(Duration::from_secs(60));
+        for v in [5.0, 0.5, 2.0, 3.0].iter() {
+            winvec.push(*v);
+        }
+        assert_eq!(winvec.sum_while(|&v| v > 1.0), 5.0);
+    }
+
+    #[test]
+    fn compact_if_skips_below_threshold() {
+        let mut winvec = WinVec::with_duration(Duration::from_secs(10));
+        for el in 0..3 {
+            winvec.push_with_timestamp(el, ago(20));
+        }
+        winvec.push(3);
+
+        winvec.compact_if(0.9);
+        assert_eq!(winvec.0.len(), 4);
+        winvec.compact_if(0.5);
+        assert_eq!(winvec.0.len(), 1);
+    }
+
+    #[test]
+    fn compact_if_counts_unsorted_expired() {
+        let mut winvec = WinVec::with_duration(Duration::from_secs(10));
+        winvec.push(0);
+        winvec.push_with_timestamp(1, ago(20));
+        winvec.push_with_timestamp(2, ago(20));
+        winvec.compact_if(0.5);
+        assert_eq!(winvec.0.len(), 1);
+    }
+
+    #[test]
+    fn drain_while_takes_leading_prefix() {
+        let mut winvec = staggered();
+        assert_eq!(winvec.drain_while(|&v| v < 3), vec![1, 2]);
+        assert_eq!(winvec.iter().collect::<Vec<_>>(), vec![3, 4]);
+    }
+
+    #[test]
+    fn replace_all_keeps_timestamps() {
+        let mut winvec = staggered();
+        let before = winvec.timestamps().collect::<Vec<_>>();
+        winvec.replace_all(|v| *v *= 2);
+        assert_eq!(winvec.iter().collect::<Vec<_>>(), vec![2, 4, 6, 8]);
+        assert_eq!(winvec.timestamps().collect::<Vec<_>>(), before);
+    }
+
+    #[test]
+    fn retain_into_returns_survivors() {
+        let mut winvec = staggered();
+        let kept = winvec.retain_into(|&v| v > 2);
+        assert_eq!(kept, winvec.0);
+        assert_eq!(kept.iter().map(|e| e.1).collect::<Vec<_>>(), vec![3, 4]);
+    }
+
+    #[test]
+    fn expired_len_does_not_purge() {
+        let mut winvec = WinVec::with_duration(Duration::from_secs(10));
+        winvec.push_with_timestamp(1, ago(20));
+        winvec.push_with_timestamp(2, ago(20));
+        winvec.push(3);
+        assert_eq!(winvec.expired_len(), 2);
+        assert_eq!(winvec.0.len(), 3);
+        assert_eq!(winvec.len(), 1);
+        assert_eq!(winvec.expired_len(), 0);
+    }
+
+    #[test]
+    fn live_len_does_not_purge() {
+        let mut winvec = WinVec::with_duration(Duration::from_secs(10));
+        winvec.push_with_timestamp(1, ago(20));
+        winvec.push(2);
+        assert_eq!(winvec.live_len(), 1);
+        assert_eq!(winvec.0.len(), 2);
+        assert_eq!(winvec.len(), winvec.live_len());
+    }
+
+    #[test]
+    fn iter_expired_previews_purge() {
+        let mut winvec = WinVec::with_duration(Duration::from_secs(10));
+        winvec.push_with_timestamp(1, ago(20));
+        winvec.push(2);
+        winvec.push_with_timestamp(3, ago(20));
+        assert_eq!(winvec.iter_expired().collect::<Vec<_>>(), vec![&1, &3]);
+        assert_eq!(winvec.0.len(), 3);
+        assert_eq!(winvec.len(), 1);
+    }
+
+    #[test]
+    fn bucketed_sum_per_slice() {
+        let mut winvec = WinVec::with_duration(Duration::from_secs(30));
+        winvec.push_with_timestamp(1.0, ago(25));
+        winvec.push_with_timestamp(2.0, ago(15));
+        winvec.push_with_timestamp(3.0, ago(5));
+        winvec.push_with_timestamp(4.0, ago(4));
+        winvec.push_with_timestamp(100.0, ago(40));
+        assert_eq!(
+            winvec.bucketed_sum(Duration::from_secs(10)),
+            vec![1.0, 2.0, 7.0]
+        );
+        assert_eq!(winvec.bucketed_sum(Duration::from_secs(20)), vec![3.0, 7.0]);
+        assert!(winvec.bucketed_sum(Duration::from_secs(0)).is_empty());
+    }
+
+    #[test]
+    fn clone_live_into_reuses_buffer() {
+        let mut winvec = WinVec::with_duration(Duration::from_secs(60));
+        let mut buf = Vec::new();
+        winvec.push(1);
+        winvec.clone_live_into(&mut buf);
+        assert_eq!(buf, vec![1]);
+        winvec.push(2);
+        winvec.clone_live_into(&mut buf);
+        assert_eq!(buf, vec![1, 2]);
+    }
+
+    #[test]
+    fn most_common_by_count_then_recency() {
+        let mut winvec = WinVec::with_duration(Duration::from_secs(60));
+        for v in ["a", "b", "a", "c", "b", "a", "d"].iter() {
+            winvec.push(*v);
+        }
+        assert_eq!(winvec.most_common(3), vec![("a", 3), ("b", 2), ("d", 1)]);
+    }
+
+    #[test]
+    fn chunks_with_short_tail() {
+        let mut winvec = WinVec::with_duration(Duration::from_secs(60));
+        for el in 1..=5 {
+            winvec.push(el);
+        }
+        let chunks = winvec.chunks(2).collect::<Vec<_>>();
+        assert_eq!(chunks, vec![vec![1, 2], vec![3, 4], vec![5]]);
+    }
+
+    #[test]
+    fn drained_expired_entries_move_to_archive() {
+        let mut winvec = WinVec::with_duration(Duration::from_secs(10));
+        let old = ago(20);
+        winvec.push_with_timestamp(1, old);
+        winvec.push(2);
+
+        let mut archive = WinVec::with_duration(Duration::from_secs(60));
+        for (instant, el) in winvec.drain_expired_with_timestamps() {
+            archive.push_with_timestamp(el, instant);
+        }
+        assert_eq!(winvec.iter().collect::<Vec<_>>(), vec![2]);
+        assert_eq!(archive.iter().collect::<Vec<_>>(), vec![1]);
+        assert_eq!(archive.timestamps().collect::<Vec<_>>(), vec![old]);
+    }
+
+    #[test]
+    fn scan_live_cumulative_sum() {
+        let mut winvec = staggered();
+        winvec.push_with_timestamp(100, ago(90));
+        let sums = winvec.scan_live(0, |acc, v| *acc + v);
+        assert_eq!(sums, vec![1, 3, 6, 10]);
+    }
+
+    #[test]
+    fn decayed_count_halves_per_half_life() {
+        let half_life = Duration::from_secs(10);
+        for (secs, expected) in [(0, 1.0), (10, 0.5), (20, 0.25)].iter() {
+            let mut winvec = WinVec::with_duration(Duration::from_secs(60));
+            winvec.push_with_timestamp((), ago(*secs));
+            assert!(approx(winvec.decayed_count(half_life), *expected));
+        }
+    }
+
+    #[test]
+    fn for_each_expired_visits_expired_only() {
+        let mut winvec = WinVec::with_duration(Duration::from_secs(10));
+        let old = ago(20);
+        winvec.push_with_timestamp(1, old);
+        winvec.push(2);
+        winvec.push_with_timestamp(3, old);
+        winvec.push(4);
+
+        let mut expired = Vec::new();
+        winvec.for_each_expired(|instant, el| expired.push((instant, el)));
+        expired.sort_by_key(|e| e.1);
+        assert_eq!(expired, vec![(old, 1), (old, 3)]);
+        assert_eq!(winvec.0.iter().map(|e| e.1).collect::<Vec<_>>(), vec![2, 4]);
+    }
+
+    #[test]
+    fn is_sorted_detects_out_of_order() {
+        let mut winvec = staggered();
+        assert!(winvec.is_sorted());
+        winvec.push_with_timestamp(5, ago(50));
+        assert!(!winvec.is_sorted());
+    }
+
+    #[test]
+    fn restore_rolls_back() {
+        let mut winvec = staggered();
+        let before = winvec.timestamps().collect::<Vec<_>>();
+        let cp = winvec.checkpoint();
+        winvec.push(5);
+        winvec.retain_last(1);
+        winvec.restore(cp);
+        assert_eq!(winvec.iter().collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+        assert_eq!(winvec.timestamps().collect::<Vec<_>>(), before);
+    }
+
+    #[test]
+    fn dedup_exact_collapses_replays() {
+        let mut winvec = WinVec::with_duration(Duration::from_secs(60));
+        let instant = ago(10);
+        for _ in 0..3 {
+            winvec.push_with_timestamp(1, instant);
+        }
+        winvec.push_with_timestamp(1, ago(5));
+        winvec.dedup_exact();
+        assert_eq!(winvec.len(), 2);
+    }
+
+    #[test]
+    fn window_ratio_of_recent_entries() {
+        let mut winvec = staggered();
+        assert_eq!(winvec.window_ratio(Duration::from_secs(25)), 0.5);
+        assert_eq!(winvec.window_ratio(Duration::from_secs(15)), 0.25);
+        let mut empty = WinVec::<i32>::with_duration(Duration::from_secs(60));
+        assert_eq!(empty.window_ratio(Duration::from_secs(15)), 0.0);
+    }
+
+    #[test]
+    fn merge_sorted_keeps_global_order() {
+        let mut winvec = WinVec::with_duration(Duration::from_secs(60));
+        winvec.push_with_timestamp(1, ago(40));
+        winvec.push_with_timestamp(3, ago(20));
+        winvec.merge_sorted(vec![(ago(30), 2), (ago(10), 4)]);
+        assert!(winvec.is_sorted());
+        assert_eq!(winvec.iter().collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn merge_sorted_sorts_unsorted_inputs() {
+        let mut winvec = WinVec::with_duration(Duration::from_secs(60));
+        winvec.push_with_timestamp(3, ago(20));
+        winvec.push_with_timestamp(1, ago(40));
+        winvec.merge_sorted(vec![(ago(10), 4), (ago(30), 2)]);
+        assert!(winvec.is_sorted());
+        assert_eq!(winvec.iter().collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+
+        winvec.truncate_to_duration(Duration::from_secs(25));
+        assert_eq!(winvec.iter().collect::<Vec<_>>(), vec![3, 4]);
+    }
+
+    #[test]
+    fn time_until_half_expired_is_median_remaining() {
+        let mut winvec = staggered();
+        let remaining = winvec.time_until_half_expired().unwrap();
+        assert!((remaining.as_secs_f64() - 30.0).abs() < 0.1);
+        let mut empty = WinVec::<i32>::with_duration(Duration::from_secs(60));
+        assert_eq!(empty.time_until_half_expired(), None);
+    }
+
+    #[test]
+    fn retain_last_distinct_keeps_newest_occurrences() {
+        let mut winvec = WinVec::with_duration(Duration::from_secs(60));
+        let newest_b = ago(5);
+        winvec.push_with_timestamp("a", ago(40));
+        winvec.push_with_timestamp("b", ago(30));
+        winvec.push_with_timestamp("c", ago(20));
+        winvec.push_with_timestamp("a", ago(10));
+        winvec.push_with_timestamp("b", newest_b);
+        winvec.retain_last_distinct(2);
+        assert_eq!(winvec.iter().collect::<Vec<_>>(), vec!["a", "b"]);
+        assert_eq!(winvec.timestamps().last(), Some(newest_b));
+    }
+}
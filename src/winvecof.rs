@@ -0,0 +1,110 @@
+use std::time::{Duration, Instant};
+
+/// An element that carries its own timestamp
+///
+/// Implement this for types that already record when they happened, so they can be stored in a
+/// `WinVecOf` without a separate `Instant` per element.
+pub trait Timestamped {
+    /// The instant this element's age is measured from
+    fn instant(&self) -> Instant;
+}
+
+/// Windowed Vector of self-timestamped elements
+///
+/// Like `WinVec`, but elements are stored bare and their age is read from `Timestamped::instant`
+/// during purge, keeping the element's own time field authoritative.
+///
+/// We purge old keys on read, rather than on insert.
+/// You can specify the duration via `with_duration()`.
+/// Add elements with `push`.
+/// View elements via `iter` and `into_iter`
+#[derive(Clone)]
+pub struct WinVecOf<T>(Vec<T>, Duration);
+
+impl<T: Timestamped> WinVecOf<T> {
+    /// Create a new Windowed Vector with a set duration
+    pub fn with_duration(dur: Duration) -> Self {
+        WinVecOf(Vec::new(), dur)
+    }
+
+    /// Push an element into the windowed array
+    pub fn push(&mut self, el: T) {
+        self.0.push(el);
+    }
+
+    pub fn from_vec(vec: Vec<T>, dur: Duration) -> Self {
+        WinVecOf(vec, dur)
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.1
+    }
+
+    /// Returns the number of elements within the collection.
+    /// We purge and then return the new length.
+    pub fn len(&mut self) -> usize {
+        self.purge();
+        self.0.len()
+    }
+
+    /// Returns true if the collection has no live elements.
+    pub fn is_empty(&mut self) -> bool {
+        self.len() == 0
+    }
+
+    /// Purges & Returns an Iterator over references to the elements
+    pub fn iter(&mut self) -> impl '_ + Iterator<Item = &T> {
+        self.purge();
+        self.0.iter()
+    }
+
+    /// Purge expired entries by calculating elapsed time and filtering values past our specified
+    /// duration.
+    fn purge(&mut self) {
+        let dur = self.1;
+        self.0.retain(|e| e.instant().elapsed() < dur);
+    }
+}
+
+impl<T: Timestamped> IntoIterator for WinVecOf<T> {
+    type Item = T;
+    type IntoIter = std::vec::IntoIter<Self::Item>;
+
+    fn into_iter(mut self) -> Self::IntoIter {
+        self.purge();
+        self.0.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Event {
+        at: Instant,
+        id: u32,
+    }
+
+    impl Timestamped for Event {
+        fn instant(&self) -> Instant {
+            self.at
+        }
+    }
+
+    #[test]
+    fn purges_by_element_timestamp() {
+        let mut winvec = WinVecOf::with_duration(Duration::from_secs(10));
+        let now = Instant::now();
+        winvec.push(Event {
+            at: now - Duration::from_secs(20),
+            id: 1,
+        });
+        winvec.push(Event { at: now, id: 2 });
+        assert_eq!(winvec.len(), 1);
+        assert_eq!(winvec.iter().map(|e| e.id).collect::<Vec<_>>(), vec![2]);
+        assert_eq!(
+            winvec.into_iter().map(|e| e.id).collect::<Vec<_>>(),
+            vec![2]
+        );
+    }
+}